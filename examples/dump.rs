@@ -3,9 +3,8 @@ use las::{Writer, Point, Color, Builder, GpsTimeType};
 use las::point::Format;
 use palette::{Gradient, Hsv, LinSrgb};
 use chrono::TimeZone;
-use std::f64;
 
-use livox::{Sdk, LidarMode, DataPoint, DataPacket, LidarStateMask, CoordinateSystem};
+use livox::{Sdk, LidarMode, DataPoint, DataPacket, LidarStateMask, CoordinateSystem, PacketSource};
 
 fn build_point(x: f32, y: f32, z: f32, reflectivity: u8, time: Option<f64>) -> Point {
     let grad = Gradient::new(vec![
@@ -31,17 +30,16 @@ fn save_points(packet: DataPacket, las_writer: &mut las::Write) {
         let tm = Some((packet.timestamp - 1_000_000_000) as f64 + i as f64 * 0.000_01);
         match point {
             DataPoint::Spherical(p) => {
-                let pi = f64::consts::PI;
-                let x = p.depth * p.phi.cos() * p.theta.sin();
-                let y = p.depth * p.phi.sin() * p.theta.sin();
-                let z = p.depth * p.theta.cos();
-                let p = build_point(x, y, z, p.reflectivity, tm);
+                let c = p.to_cartesian();
+                let p = build_point(c.x, c.y, c.z, c.reflectivity, tm);
                 las_writer.write(p).unwrap();
             }
             DataPoint::Cartesian(p) => {
                 let p = build_point(p.x, p.y, p.z, p.reflectivity, tm);
                 las_writer.write(p).unwrap();
             }
+            // IMU samples carry no position; they are not written to the LAS.
+            DataPoint::Imu(_) => {}
         }
     }
 }
@@ -75,7 +73,7 @@ fn main() {
         let mut ds = dev.start_sampling().unwrap();
         let now = Instant::now();
         while now.elapsed() < Duration::from_millis(5_000) {
-            match ds.next() {
+            match ds.next_timeout(Duration::from_millis(100)) {
                 Some(data_packet) => {
                     save_points(data_packet, &mut las_writer);
                 }
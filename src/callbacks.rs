@@ -1,10 +1,10 @@
 use std::collections::HashMap;
-use std::sync::Mutex;
-use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Sender, SyncSender, Receiver, TrySendError};
 use num_traits::FromPrimitive;
 
 use crate::enums::*;
-use crate::datapacket::DataPacket;
+use crate::datapacket::{DataPacket, Extrinsic};
 
 lazy_static! {
     pub static ref BROADCAST_PIPE: Mutex<Option<Sender<livox_sys::BroadcastDeviceInfo>>> = Mutex::new(None);
@@ -41,18 +41,63 @@ pub extern fn device_state_update_cb(devinfo: *const livox_sys::DeviceInfo, _eve
     );
 }
 
+/// The sending half of a `DataStream`'s bounded channel, along with enough
+/// state for `data_cb` to honor the stream's overflow policy. The receiver is
+/// shared so that, under `DropOldest`, the callback thread can pop the oldest
+/// queued packet to make room for the newest.
+pub struct DataPipe {
+    pub sender: SyncSender<DataPacket>,
+    pub receiver: Arc<Mutex<Receiver<DataPacket>>>,
+    pub policy: OverflowPolicy,
+}
+
 lazy_static! {
-    pub static ref DATA_PIPES: Mutex<HashMap<u8, Sender<DataPacket>>> = Mutex::new(HashMap::new());
+    pub static ref DATA_PIPES: Mutex<HashMap<u8, DataPipe>> = Mutex::new(HashMap::new());
+}
+
+lazy_static! {
+    /// Per-device extrinsic transform, keyed by handle. Absent means identity.
+    pub static ref EXTRINSICS: Mutex<HashMap<u8, Extrinsic>> = Mutex::new(HashMap::new());
 }
 
 pub extern fn data_cb(handle: u8, data: *mut livox_sys::LivoxEthPacket, data_size: u32, _user_data: *mut std::ffi::c_void) {
-    match &(*DATA_PIPES.lock().unwrap()).get(&handle) {
-        Some(sender) => {
-            let dp = DataPacket::from((data, data_size));
-            sender.send(dp).unwrap();
-        }
-        None => {
+    // Take our own handles to the pipe and release the global lock immediately:
+    // under `Block` the send below can park on a full channel, and holding
+    // DATA_PIPES across that would deadlock `DataStream::drop` (which needs the
+    // same lock) and stall every other device's delivery.
+    let (sender, receiver, policy) = {
+        let pipes = DATA_PIPES.lock().unwrap();
+        match pipes.get(&handle) {
+            Some(pipe) => (pipe.sender.clone(), pipe.receiver.clone(), pipe.policy),
             // This can happen after the data stream is closed
+            None => return,
+        }
+    };
+
+    let mut dp = DataPacket::from((data, data_size));
+    if let Some(ext) = (*EXTRINSICS.lock().unwrap()).get(&handle) {
+        dp.apply_extrinsic(ext);
+    }
+
+    // Never `.unwrap()` the send: a stalled or dropped receiver must not panic
+    // the SDK's callback thread.
+    match policy {
+        OverflowPolicy::Block => {
+            let _ = sender.send(dp);
+        }
+        OverflowPolicy::DropOldest => {
+            let mut dp = dp;
+            loop {
+                match sender.try_send(dp) {
+                    Ok(()) => break,
+                    Err(TrySendError::Full(returned)) => {
+                        // Make room by discarding the oldest packet.
+                        let _ = receiver.lock().unwrap().try_recv();
+                        dp = returned;
+                    }
+                    Err(TrySendError::Disconnected(_)) => break,
+                }
+            }
         }
     }
 }
@@ -1,4 +1,4 @@
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::Cursor;
 
 #[derive(Debug)]
@@ -7,6 +7,24 @@ pub struct CartesianPoint {
     pub y: f32,
     pub z: f32,
     pub reflectivity: u8,
+    /// Per-point tag (return number, noise/confidence bits). Zero for the
+    /// non-extended data types that carry no tag. See `PointTag`.
+    pub tag: u8,
+}
+
+impl CartesianPoint {
+    /// Converts this point to spherical coordinates, preserving reflectivity.
+    pub fn to_spherical(&self) -> SphericalPoint {
+        let depth = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        let theta = if depth == 0.0 { 0.0 } else { (self.z / depth).acos() };
+        SphericalPoint{
+            depth: depth,
+            theta: theta,
+            phi: self.y.atan2(self.x),
+            reflectivity: self.reflectivity,
+            tag: self.tag,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -14,18 +32,159 @@ pub struct SphericalPoint {
     pub depth: f32,
     pub theta: f32,
     pub phi: f32,
-    pub reflectivity: u8
+    pub reflectivity: u8,
+    /// Per-point tag. See `CartesianPoint::tag` and `PointTag`.
+    pub tag: u8,
+}
+
+impl SphericalPoint {
+    /// Converts this point to Cartesian coordinates, preserving reflectivity.
+    pub fn to_cartesian(&self) -> CartesianPoint {
+        CartesianPoint{
+            x: self.depth * self.phi.cos() * self.theta.sin(),
+            y: self.depth * self.phi.sin() * self.theta.sin(),
+            z: self.depth * self.theta.cos(),
+            reflectivity: self.reflectivity,
+            tag: self.tag,
+        }
+    }
+}
+
+/// The per-point tag byte carried by the extended/dual/triple data types,
+/// broken out into its bitfields following the Livox layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointTag {
+    pub raw: u8,
+    /// Bits 0-1: spatial confidence of the point.
+    pub spatial_confidence: u8,
+    /// Bits 2-3: intensity confidence of the point.
+    pub intensity_confidence: u8,
+    /// Bits 4-5: which return this point represents.
+    pub return_number: u8,
+}
+
+impl From<u8> for PointTag {
+    fn from(raw: u8) -> PointTag {
+        PointTag{
+            raw: raw,
+            spatial_confidence: raw & 0b11,
+            intensity_confidence: (raw >> 2) & 0b11,
+            return_number: (raw >> 4) & 0b11,
+        }
+    }
+}
+
+/// A single IMU sample: three-axis gyroscope (rad/s) and accelerometer (g).
+#[derive(Debug)]
+pub struct ImuSample {
+    pub gyro_x: f32,
+    pub gyro_y: f32,
+    pub gyro_z: f32,
+    pub accel_x: f32,
+    pub accel_y: f32,
+    pub accel_z: f32,
+}
+
+/// A rigid-body transform applied to every emitted point, expressing points in
+/// a user-defined frame (`p' = R·p + t`). Mirrors the per-device extrinsic
+/// matrix stored elsewhere in the fleet. The identity transform leaves points
+/// untouched.
+#[derive(Debug, Clone, Copy)]
+pub struct Extrinsic {
+    pub rotation: [[f32; 3]; 3],
+    pub translation: [f32; 3],
+}
+
+impl Extrinsic {
+    /// The identity transform, applied by default so points are unchanged.
+    pub fn identity() -> Extrinsic {
+        Extrinsic{
+            rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            translation: [0.0, 0.0, 0.0],
+        }
+    }
+
+    /// Applies `p' = R·p + t` to a Cartesian coordinate triple.
+    fn apply(&self, p: [f32; 3]) -> [f32; 3] {
+        let mut out = self.translation;
+        for i in 0..3 {
+            for j in 0..3 {
+                out[i] += self.rotation[i][j] * p[j];
+            }
+        }
+        out
+    }
+}
+
+impl Default for Extrinsic {
+    fn default() -> Extrinsic {
+        Extrinsic::identity()
+    }
 }
 
 #[derive(Debug)]
 pub enum DataPoint {
     Cartesian(CartesianPoint),
     Spherical(SphericalPoint),
+    Imu(ImuSample),
+}
+
+/// The clock domain a packet's `timestamp` was sampled against, derived from
+/// the Livox `timestamp_type`. Knowing the domain lets captures taken with
+/// different synchronization sources be compared meaningfully.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockDomain {
+    /// Type 0: free-running nanoseconds, not synchronized to an external clock.
+    Unsynced,
+    /// Type 1: synchronized to GPS/UTC.
+    Gps,
+    /// Type 3: synchronized via PTP (IEEE 1588).
+    Ptp,
+}
+
+/// The non-PPS portion of the Livox status code, broken out into named fields.
+/// The two-bit sub-status fields follow the Livox convention of 0 = normal,
+/// 1 = warning, 2 = error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorFlags {
+    pub raw: u32,
+    pub temp_status: u8,
+    pub volt_status: u8,
+    pub motor_status: u8,
+    pub dirty_warn: u8,
+    pub firmware_error: bool,
+    pub device_status: bool,
+}
+
+impl ErrorFlags {
+    fn from_raw(raw: u32) -> ErrorFlags {
+        ErrorFlags{
+            raw: raw,
+            temp_status: (raw & 0b11) as u8,
+            volt_status: ((raw >> 2) & 0b11) as u8,
+            motor_status: ((raw >> 4) & 0b11) as u8,
+            dirty_warn: ((raw >> 6) & 0b11) as u8,
+            firmware_error: (raw >> 8) & 1 != 0,
+            device_status: (raw >> 10) & 1 != 0,
+        }
+    }
+}
+
+impl Default for ErrorFlags {
+    fn default() -> ErrorFlags {
+        ErrorFlags::from_raw(0)
+    }
 }
 
 pub struct DataPacket {
     pub timestamp: u64,
     pub points: Vec<DataPoint>,
+    /// Which clock the `timestamp` is expressed against.
+    pub clock: ClockDomain,
+    /// PPS (pulse-per-second) sync status: `true` when the signal is locked.
+    pub pps_locked: bool,
+    /// The remaining device status bits, decoded into named fields.
+    pub errors: ErrorFlags,
 }
 
 impl DataPacket {
@@ -42,6 +201,7 @@ impl DataPacket {
                 y: y as f32 / 1000.0,
                 z: z as f32 / 1000.0,
                 reflectivity: reflectivity,
+                tag: 0,
             }));
         }
     }
@@ -59,9 +219,204 @@ impl DataPacket {
                 theta: theta as f32 / 100.0 / 180.0 * 3.14159265,
                 phi: phi as f32 / 100.0 / 180.0 * 3.14159265,
                 reflectivity: reflectivity,
+                tag: 0,
+            }));
+        }
+    }
+
+    /// Reads a Cursor-decoded raw Cartesian triple into a scaled point.
+    fn cartesian_point(rdr: &mut Cursor<Vec<u8>>, tag: u8) -> CartesianPoint {
+        let x = rdr.read_i32::<LittleEndian>().unwrap();
+        let y = rdr.read_i32::<LittleEndian>().unwrap();
+        let z = rdr.read_i32::<LittleEndian>().unwrap();
+        let reflectivity = rdr.read_u8().unwrap();
+        CartesianPoint{
+            x: x as f32 / 1000.0,
+            y: y as f32 / 1000.0,
+            z: z as f32 / 1000.0,
+            reflectivity: reflectivity,
+            tag: tag,
+        }
+    }
+
+    /// Extended Cartesian (data_type 2): Cartesian plus a per-point tag byte.
+    fn add_extended_cartesian(&mut self, data: &[u8], npoints: usize) {
+        assert!(data.len() == npoints * 14);
+        let mut rdr = Cursor::new(data.to_vec());
+        for _ in 0..npoints {
+            let mut p = Self::cartesian_point(&mut rdr, 0);
+            p.tag = rdr.read_u8().unwrap();
+            self.points.push(DataPoint::Cartesian(p));
+        }
+    }
+
+    /// Extended Spherical (data_type 3): Spherical plus a per-point tag byte.
+    fn add_extended_spherical(&mut self, data: &[u8], npoints: usize) {
+        assert!(data.len() == npoints * 10);
+        let mut rdr = Cursor::new(data.to_vec());
+        for _ in 0..npoints {
+            let depth = rdr.read_u32::<LittleEndian>().unwrap();
+            let theta = rdr.read_u16::<LittleEndian>().unwrap();
+            let phi = rdr.read_u16::<LittleEndian>().unwrap();
+            let reflectivity = rdr.read_u8().unwrap();
+            let tag = rdr.read_u8().unwrap();
+            self.points.push(DataPoint::Spherical(SphericalPoint{
+                depth: depth as f32 / 1000.0,
+                theta: theta as f32 / 100.0 / 180.0 * 3.14159265,
+                phi: phi as f32 / 100.0 / 180.0 * 3.14159265,
+                reflectivity: reflectivity,
+                tag: tag,
             }));
         }
     }
+
+    /// Dual (data_type 4) and triple (data_type 7) extended Cartesian: each beam
+    /// carries `nreturns` Cartesian points, each with its own tag byte.
+    fn add_multi_cartesian(&mut self, data: &[u8], npoints: usize, nreturns: usize) {
+        assert!(data.len() == npoints * nreturns * 14);
+        let mut rdr = Cursor::new(data.to_vec());
+        for _ in 0..npoints {
+            for _ in 0..nreturns {
+                let mut p = Self::cartesian_point(&mut rdr, 0);
+                p.tag = rdr.read_u8().unwrap();
+                self.points.push(DataPoint::Cartesian(p));
+            }
+        }
+    }
+
+    /// Dual (data_type 5) and triple (data_type 8) extended Spherical: one shared
+    /// direction per beam, followed by `nreturns` (depth, reflectivity, tag)
+    /// triples. This matches the Livox `LivoxDualExtendSpherPoint` /
+    /// `LivoxTripleExtendSpherPoint` layout, where `theta`/`phi` precede the
+    /// per-return fields.
+    fn add_multi_spherical(&mut self, data: &[u8], npoints: usize, nreturns: usize) {
+        assert!(data.len() == npoints * (4 + 6 * nreturns));
+        let mut rdr = Cursor::new(data.to_vec());
+        for _ in 0..npoints {
+            let theta = rdr.read_u16::<LittleEndian>().unwrap();
+            let phi = rdr.read_u16::<LittleEndian>().unwrap();
+            for _ in 0..nreturns {
+                let depth = rdr.read_u32::<LittleEndian>().unwrap();
+                let reflectivity = rdr.read_u8().unwrap();
+                let tag = rdr.read_u8().unwrap();
+                self.points.push(DataPoint::Spherical(SphericalPoint{
+                    depth: depth as f32 / 1000.0,
+                    theta: theta as f32 / 100.0 / 180.0 * 3.14159265,
+                    phi: phi as f32 / 100.0 / 180.0 * 3.14159265,
+                    reflectivity: reflectivity,
+                    tag: tag,
+                }));
+            }
+        }
+    }
+
+    /// IMU (data_type 6): three gyroscope and three accelerometer floats.
+    fn add_imu(&mut self, data: &[u8], nsamples: usize) {
+        assert!(data.len() == nsamples * 24);
+        let mut rdr = Cursor::new(data.to_vec());
+        for _ in 0..nsamples {
+            self.points.push(DataPoint::Imu(ImuSample{
+                gyro_x: rdr.read_f32::<LittleEndian>().unwrap(),
+                gyro_y: rdr.read_f32::<LittleEndian>().unwrap(),
+                gyro_z: rdr.read_f32::<LittleEndian>().unwrap(),
+                accel_x: rdr.read_f32::<LittleEndian>().unwrap(),
+                accel_y: rdr.read_f32::<LittleEndian>().unwrap(),
+                accel_z: rdr.read_f32::<LittleEndian>().unwrap(),
+            }));
+        }
+    }
+
+    /// Applies an extrinsic transform to every point. Spherical points are
+    /// converted to Cartesian for the transform and converted back afterwards,
+    /// so the packet's coordinate system is preserved.
+    pub(crate) fn apply_extrinsic(&mut self, ext: &Extrinsic) {
+        for point in self.points.iter_mut() {
+            match point {
+                DataPoint::Cartesian(p) => {
+                    let [x, y, z] = ext.apply([p.x, p.y, p.z]);
+                    p.x = x;
+                    p.y = y;
+                    p.z = z;
+                }
+                DataPoint::Spherical(p) => {
+                    let c = p.to_cartesian();
+                    let [x, y, z] = ext.apply([c.x, c.y, c.z]);
+                    *p = CartesianPoint{ x: x, y: y, z: z, reflectivity: p.reflectivity, tag: p.tag }.to_spherical();
+                }
+                // IMU samples are not spatial points; the transform does not
+                // apply to them.
+                DataPoint::Imu(_) => {}
+            }
+        }
+    }
+
+    /// Reconstructs a packet from the raw little-endian payload written by the
+    /// recorder. Only the tag-less Cartesian (`data_type` 0) and Spherical
+    /// (`data_type` 1) layouts are supported — the same ones `recordable_type`
+    /// permits on the way in — so a round-tripped packet's points match the
+    /// originals. Synchronization metadata (clock domain, PPS, error flags) is
+    /// not persisted and is reset to defaults on replay.
+    pub(crate) fn from_raw(data_type: u8, timestamp: u64, payload: &[u8]) -> DataPacket {
+        let mut dp = DataPacket{
+            timestamp: timestamp,
+            points: vec!(),
+            clock: ClockDomain::Unsynced,
+            pps_locked: false,
+            errors: ErrorFlags::default(),
+        };
+        if data_type == 0 {
+            dp.add_cartesian(payload, payload.len() / 13);
+        } else if data_type == 1 {
+            dp.add_spherical(payload, payload.len() / 9);
+        } else {
+            panic!("Unknown data type {}", data_type);
+        }
+        dp
+    }
+
+    /// Returns the `data_type` this packet can be recorded as, or `None` if it
+    /// cannot be round-tripped by the length-delimited record format. Only
+    /// homogeneous, tag-less Cartesian (0) or Spherical (1) packets qualify;
+    /// extended/dual/triple packets carry per-point tags and IMU packets carry
+    /// no points, neither of which the format preserves.
+    pub(crate) fn recordable_type(&self) -> Option<u8> {
+        let data_type = match self.points.first()? {
+            DataPoint::Cartesian(_) => 0,
+            DataPoint::Spherical(_) => 1,
+            DataPoint::Imu(_) => return None,
+        };
+        let ok = self.points.iter().all(|p| match (data_type, p) {
+            (0, DataPoint::Cartesian(p)) => p.tag == 0,
+            (1, DataPoint::Spherical(p)) => p.tag == 0,
+            _ => false,
+        });
+        if ok { Some(data_type) } else { None }
+    }
+
+    /// Serializes the points back into the on-the-wire little-endian layout for
+    /// the given `data_type`, which must be one returned by `recordable_type`.
+    pub(crate) fn serialize_payload(&self, data_type: u8) -> Vec<u8> {
+        let mut buf = vec!();
+        for point in self.points.iter() {
+            match point {
+                DataPoint::Cartesian(p) => {
+                    buf.write_i32::<LittleEndian>((p.x * 1000.0).round() as i32).unwrap();
+                    buf.write_i32::<LittleEndian>((p.y * 1000.0).round() as i32).unwrap();
+                    buf.write_i32::<LittleEndian>((p.z * 1000.0).round() as i32).unwrap();
+                    buf.write_u8(p.reflectivity).unwrap();
+                }
+                DataPoint::Spherical(p) => {
+                    buf.write_u32::<LittleEndian>((p.depth * 1000.0).round() as u32).unwrap();
+                    buf.write_u16::<LittleEndian>((p.theta / 3.14159265 * 180.0 * 100.0).round() as u16).unwrap();
+                    buf.write_u16::<LittleEndian>((p.phi / 3.14159265 * 180.0 * 100.0).round() as u16).unwrap();
+                    buf.write_u8(p.reflectivity).unwrap();
+                }
+                // IMU samples are never recorded; `recordable_type` rejects them.
+                DataPoint::Imu(_) => {}
+            }
+        }
+        buf
+    }
 }
 
 impl From<(*mut livox_sys::LivoxEthPacket, u32)> for DataPacket {
@@ -72,45 +427,294 @@ impl From<(*mut livox_sys::LivoxEthPacket, u32)> for DataPacket {
         let err_code = unsafe { (*data).err_code };
         let data_type = unsafe { (*data).data_type };
 
-        // Bit 9 is the PPS status - 0 is no signal, 1 is signal OK.
-        if err_code&!(1 << 9) != 0 {
-            panic!("Error code in data packet: {}", err_code);
-        }
-
         if version != 5 {
             panic!("Unknown data version {} encountered", version);
         }
-        let time = if timestamp_type == 0 {
-            // Nanoseconds, unsync'd
-            parse_timestamp(&timestamp)
-        } else {
-            panic!("Unknown timestamp type {}", timestamp_type);
-        };
+
+        // Bit 9 is the PPS status - 0 is no signal, 1 is signal OK. The rest of
+        // the status code is preserved as structured flags rather than treated
+        // as a fatal error.
+        let pps_locked = (err_code >> 9) & 1 != 0;
+        let errors = ErrorFlags::from_raw(err_code & !(1 << 9));
+
+        let (time, clock) = parse_timestamp(timestamp_type, &timestamp);
 
         let mut dp = DataPacket{
             //handle: handle,
-            //error_code: err_code,
             timestamp: time,
             points: vec!(),
+            clock: clock,
+            pps_locked: pps_locked,
+            errors: errors,
         };
-        if data_type == 0 {
-            // Cartesian
-            let raw_points = unsafe { std::slice::from_raw_parts(&(*data).data[0], data_size as usize * 13) };
-            dp.add_cartesian(raw_points, data_size as usize);
-        } else if data_type == 1 {
-            let raw_points = unsafe { std::slice::from_raw_parts(&(*data).data[0], data_size as usize * 9) };
-            dp.add_spherical(raw_points, data_size as usize);
-        } else {
-            panic!("Unknown data type {}", data_type);
+        let npoints = data_size as usize;
+        // Per-record byte widths for each Livox data type.
+        let record_len = match data_type {
+            0 => 13,            // Cartesian
+            1 => 9,             // Spherical
+            2 => 14,            // Extended Cartesian
+            3 => 10,            // Extended Spherical
+            4 => 2 * 14,        // Dual-return extended Cartesian
+            5 => 4 * 2 + 4 + 2 * 2,   // Dual-return extended Spherical
+            6 => 24,            // IMU
+            7 => 3 * 14,        // Triple-return extended Cartesian
+            8 => 4 * 3 + 4 + 2 * 3,   // Triple-return extended Spherical
+            other => panic!("Unknown data type {}", other),
+        };
+        let raw = unsafe { std::slice::from_raw_parts(&(*data).data[0], npoints * record_len) };
+        match data_type {
+            0 => dp.add_cartesian(raw, npoints),
+            1 => dp.add_spherical(raw, npoints),
+            2 => dp.add_extended_cartesian(raw, npoints),
+            3 => dp.add_extended_spherical(raw, npoints),
+            4 => dp.add_multi_cartesian(raw, npoints, 2),
+            5 => dp.add_multi_spherical(raw, npoints, 2),
+            6 => dp.add_imu(raw, npoints),
+            7 => dp.add_multi_cartesian(raw, npoints, 3),
+            8 => dp.add_multi_spherical(raw, npoints, 3),
+            other => panic!("Unknown data type {}", other),
         }
         dp
     }
 }
 
-fn parse_timestamp(data: &[u8]) -> u64 {
-    let mut val = 0;
+/// Decodes the 8-byte Livox timestamp according to its `timestamp_type` into a
+/// normalized nanosecond count plus the clock domain it belongs to.
+///
+/// * Type 0 — free-running nanoseconds, little-endian.
+/// * Type 1 — GPS/UTC sync: a 4-byte UTC word (year since 2000, month, day,
+///   hour) followed by a little-endian `u32` of microseconds into the hour. The
+///   whole word is folded into an absolute nanosecond count since the Unix
+///   epoch so that timestamps stay monotonic across hour boundaries.
+/// * Type 3 — PTP (IEEE 1588): nanoseconds since epoch, little-endian.
+fn parse_timestamp(timestamp_type: u8, data: &[u8]) -> (u64, ClockDomain) {
+    match timestamp_type {
+        0 => (read_u64_le(data), ClockDomain::Unsynced),
+        1 => {
+            let year = 2000 + data[0] as i64;
+            let month = data[1] as u32;
+            let day = data[2] as i64;
+            let hour = data[3] as u64;
+            let micros = read_u32_le(&data[4..8]) as u64;
+            let days = days_from_civil(year, month, day);
+            let secs = days as u64 * 86_400 + hour * 3_600;
+            (secs * 1_000_000_000 + micros * 1000, ClockDomain::Gps)
+        }
+        3 => (read_u64_le(data), ClockDomain::Ptp),
+        other => panic!("Unknown timestamp type {}", other),
+    }
+}
+
+/// Days since the Unix epoch (1970-01-01) for a proleptic-Gregorian date, per
+/// Howard Hinnant's `days_from_civil` algorithm.
+fn days_from_civil(year: i64, month: u32, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = (y - era * 400) as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) as i64 + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn read_u64_le(data: &[u8]) -> u64 {
+    let mut val = 0u64;
     for i in 0..8 {
-        val = val * 256 + data[i] as u64;
+        val |= (data[i] as u64) << (8 * i);
     }
     val
 }
+
+fn read_u32_le(data: &[u8]) -> u32 {
+    let mut val = 0u32;
+    for i in 0..4 {
+        val |= (data[i] as u32) << (8 * i);
+    }
+    val
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty() -> DataPacket {
+        DataPacket{
+            timestamp: 0,
+            points: vec!(),
+            clock: ClockDomain::Unsynced,
+            pps_locked: false,
+            errors: ErrorFlags::default(),
+        }
+    }
+
+    fn approx(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    fn spherical(p: &DataPoint) -> &SphericalPoint {
+        match p {
+            DataPoint::Spherical(s) => s,
+            other => panic!("expected spherical, got {:?}", other),
+        }
+    }
+
+    fn cartesian(p: &DataPoint) -> &CartesianPoint {
+        match p {
+            DataPoint::Cartesian(c) => c,
+            other => panic!("expected cartesian, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cartesian_roundtrips_scaling() {
+        let mut data = vec!();
+        data.extend_from_slice(&1000i32.to_le_bytes());
+        data.extend_from_slice(&(-2000i32).to_le_bytes());
+        data.extend_from_slice(&3000i32.to_le_bytes());
+        data.push(42);
+        let mut dp = empty();
+        dp.add_cartesian(&data, 1);
+        let c = cartesian(&dp.points[0]);
+        approx(c.x, 1.0);
+        approx(c.y, -2.0);
+        approx(c.z, 3.0);
+        assert_eq!(c.reflectivity, 42);
+        assert_eq!(c.tag, 0);
+    }
+
+    #[test]
+    fn extended_cartesian_reads_tag() {
+        let mut data = vec!();
+        data.extend_from_slice(&1000i32.to_le_bytes());
+        data.extend_from_slice(&1000i32.to_le_bytes());
+        data.extend_from_slice(&1000i32.to_le_bytes());
+        data.push(7);
+        data.push(0b0011_0110);
+        let mut dp = empty();
+        dp.add_extended_cartesian(&data, 1);
+        let c = cartesian(&dp.points[0]);
+        assert_eq!(c.reflectivity, 7);
+        assert_eq!(c.tag, 0b0011_0110);
+        let tag = PointTag::from(c.tag);
+        assert_eq!(tag.spatial_confidence, 0b10);
+        assert_eq!(tag.intensity_confidence, 0b01);
+        assert_eq!(tag.return_number, 0b11);
+    }
+
+    #[test]
+    fn extended_spherical_reads_tag() {
+        let mut data = vec!();
+        data.extend_from_slice(&5000u32.to_le_bytes());
+        data.extend_from_slice(&9000u16.to_le_bytes());
+        data.extend_from_slice(&18000u16.to_le_bytes());
+        data.push(3);
+        data.push(1);
+        let mut dp = empty();
+        dp.add_extended_spherical(&data, 1);
+        let s = spherical(&dp.points[0]);
+        approx(s.depth, 5.0);
+        assert_eq!(s.reflectivity, 3);
+        assert_eq!(s.tag, 1);
+    }
+
+    #[test]
+    fn dual_cartesian_yields_two_returns() {
+        let mut data = vec!();
+        for r in 0..2 {
+            data.extend_from_slice(&((r + 1) * 1000).to_le_bytes());
+            data.extend_from_slice(&0i32.to_le_bytes());
+            data.extend_from_slice(&0i32.to_le_bytes());
+            data.push(r as u8);
+            data.push(r as u8);
+        }
+        let mut dp = empty();
+        dp.add_multi_cartesian(&data, 1, 2);
+        assert_eq!(dp.points.len(), 2);
+        approx(cartesian(&dp.points[0]).x, 1.0);
+        approx(cartesian(&dp.points[1]).x, 2.0);
+        assert_eq!(cartesian(&dp.points[1]).tag, 1);
+    }
+
+    #[test]
+    fn dual_spherical_shares_direction_before_returns() {
+        // theta/phi come first, then each return's depth/reflectivity/tag.
+        let mut data = vec!();
+        data.extend_from_slice(&9000u16.to_le_bytes());  // theta
+        data.extend_from_slice(&18000u16.to_le_bytes()); // phi
+        data.extend_from_slice(&1000u32.to_le_bytes());  // depth 1
+        data.push(10);
+        data.push(1);
+        data.extend_from_slice(&2000u32.to_le_bytes());  // depth 2
+        data.push(20);
+        data.push(2);
+        let mut dp = empty();
+        dp.add_multi_spherical(&data, 1, 2);
+        assert_eq!(dp.points.len(), 2);
+        let first = spherical(&dp.points[0]);
+        let second = spherical(&dp.points[1]);
+        approx(first.depth, 1.0);
+        approx(second.depth, 2.0);
+        assert_eq!(first.reflectivity, 10);
+        assert_eq!(second.reflectivity, 20);
+        assert_eq!(second.tag, 2);
+        // Both returns share the same direction.
+        approx(first.theta, second.theta);
+        approx(first.phi, second.phi);
+        approx(first.theta, 9000.0 / 100.0 / 180.0 * 3.14159265);
+    }
+
+    #[test]
+    fn imu_reads_six_floats() {
+        let mut data = vec!();
+        for v in [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0].iter() {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        let mut dp = empty();
+        dp.add_imu(&data, 1);
+        match &dp.points[0] {
+            DataPoint::Imu(s) => {
+                approx(s.gyro_x, 1.0);
+                approx(s.gyro_z, 3.0);
+                approx(s.accel_x, 4.0);
+                approx(s.accel_z, 6.0);
+            }
+            other => panic!("expected imu, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn timestamp_type0_is_little_endian_nanoseconds() {
+        let data = 123_456_789u64.to_le_bytes();
+        let (ns, domain) = parse_timestamp(0, &data);
+        assert_eq!(ns, 123_456_789);
+        assert_eq!(domain, ClockDomain::Unsynced);
+    }
+
+    #[test]
+    fn timestamp_type3_is_ptp() {
+        let data = 42u64.to_le_bytes();
+        let (ns, domain) = parse_timestamp(3, &data);
+        assert_eq!(ns, 42);
+        assert_eq!(domain, ClockDomain::Ptp);
+    }
+
+    #[test]
+    fn days_from_civil_known_values() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 1, 1), 10957);
+    }
+
+    #[test]
+    fn gps_timestamp_is_absolute_and_monotonic_across_the_hour() {
+        // 2000-01-01 00:59:59.999999 then 2000-01-01 01:00:00.000000.
+        let mut a = vec![0, 1, 1, 0];
+        a.extend_from_slice(&3_599_999_999u32.to_le_bytes());
+        let mut b = vec![0, 1, 1, 1];
+        b.extend_from_slice(&0u32.to_le_bytes());
+        let (na, da) = parse_timestamp(1, &a);
+        let (nb, _) = parse_timestamp(1, &b);
+        assert_eq!(da, ClockDomain::Gps);
+        assert!(nb > na, "GPS timestamp must be monotonic across the hour: {} !> {}", nb, na);
+        // Absolute: epoch + 30 years worth of days, plus one hour.
+        assert_eq!(nb, (10957u64 * 86_400 + 3_600) * 1_000_000_000);
+    }
+}
@@ -1,22 +1,73 @@
-use std::sync::mpsc::{Receiver, channel, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, sync_channel, TryRecvError, RecvTimeoutError};
+use std::time::Duration;
 use livox_sys;
 use num_traits::ToPrimitive;
 use crate::enums::*;
-use crate::datapacket::DataPacket;
+use crate::datapacket::{DataPacket, Extrinsic};
 use crate::callbacks::*;
 
+/// A source of `DataPacket`s. Implemented by the live `DataStream` as well as
+/// the offline `ReplaySource`, so consumers (such as the LAS-writing example)
+/// can be written once and run against either a real device or a recording.
+///
+/// The efficient waiting accessors live on the trait (not just on `DataStream`)
+/// so a generic consumer is never forced back into the busy-polling `next`.
+pub trait PacketSource: Iterator<Item = DataPacket> {
+    /// Waits up to `timeout` for the next packet, returning `None` if none
+    /// arrives in time.
+    fn next_timeout(&mut self, timeout: Duration) -> Option<DataPacket>;
+
+    /// Blocks until the next packet is available, returning `None` once the
+    /// source is exhausted.
+    fn next_blocking(&mut self) -> Option<DataPacket>;
+}
+
+/// Default capacity of a `DataStream`'s bounded channel.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Outcome of a bounded receive on a `DataStream`, distinguishing a timeout
+/// (source idle but still connected) from a closed channel (source exhausted).
+enum RecvStatus {
+    Packet(DataPacket),
+    Timeout,
+    Closed,
+}
+
 pub struct DataStream {
     handle: u8,
-    receiver: Receiver<DataPacket>,
+    code: String,
+    receiver: Arc<Mutex<Receiver<DataPacket>>>,
 }
 
 impl DataStream {
-    fn new(handle: u8) -> Result<DataStream, ()> {
-        let (sender, receiver) = channel();
+    /// The broadcast code of the device this stream belongs to.
+    pub fn device_code(&self) -> &str {
+        &self.code
+    }
+
+    /// Waits up to `timeout`, reporting whether a packet arrived, the wait timed
+    /// out, or the channel has closed. Lets `MergedStream` tell an idle source
+    /// apart from an exhausted one, which `next_timeout` alone cannot.
+    fn recv_status(&mut self, timeout: Duration) -> RecvStatus {
+        match self.receiver.lock().unwrap().recv_timeout(timeout) {
+            Ok(packet) => RecvStatus::Packet(packet),
+            Err(RecvTimeoutError::Timeout) => RecvStatus::Timeout,
+            Err(RecvTimeoutError::Disconnected) => RecvStatus::Closed,
+        }
+    }
+
+    fn new(handle: u8, code: String, capacity: usize, policy: OverflowPolicy) -> Result<DataStream, ()> {
+        let (sender, receiver) = sync_channel(capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
         // @TODO: Check that there isn't already a handle there
         (*DATA_PIPES.lock().unwrap()).insert(
             handle.clone(),
-            sender,
+            DataPipe{
+                sender: sender,
+                receiver: Arc::clone(&receiver),
+                policy: policy,
+            },
         );
         unsafe {
             livox_sys::SetDataCallback(handle, Some(data_cb), 0 as *mut std::ffi::c_void);
@@ -24,24 +75,59 @@ impl DataStream {
         }
         Ok(DataStream{
             handle: handle,
+            code: code,
             receiver: receiver,
         })
     }
+
+}
+
+impl PacketSource for DataStream {
+    /// Unlike `next`, this does not busy-poll. A closed channel is the normal
+    /// end of stream and yields `None`.
+    fn next_timeout(&mut self, timeout: Duration) -> Option<DataPacket> {
+        match self.receiver.lock().unwrap().recv_timeout(timeout) {
+            Ok(packet) => {
+                Some(packet)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                None
+            }
+            // A closed channel is the normal end of stream, not a bug.
+            Err(RecvTimeoutError::Disconnected) => {
+                None
+            }
+        }
+    }
+
+    /// Returns `None` only once the stream has been closed.
+    fn next_blocking(&mut self) -> Option<DataPacket> {
+        match self.receiver.lock().unwrap().recv() {
+            Ok(packet) => {
+                Some(packet)
+            }
+            Err(_) => {
+                None
+            }
+        }
+    }
 }
 
 impl Iterator for DataStream {
     type Item = DataPacket;
 
     fn next(&mut self) -> Option<DataPacket> {
-        match self.receiver.try_recv() {
+        match self.receiver.lock().unwrap().try_recv() {
             Ok(packet) => {
                 Some(packet)
             }
+            // Both an empty channel and a closed one simply mean "no packet
+            // right now"; end of stream is not a panic.
             Err(TryRecvError::Empty) => {
                 None
             }
             Err(TryRecvError::Disconnected) => {
-                panic!("Received disconnect error in DataStream iterator!");
+                None
             }
         }
     }
@@ -59,21 +145,28 @@ impl Drop for DataStream {
 
 pub struct Device {
     handle: u8,
+    code: String,
 }
 
 /// Interface for a single Livox device.
 impl Device {
     // @TODO: This really shouldn't be public
-    pub fn new(handle: u8) -> Result<Device, ()> {
+    pub fn new(handle: u8, code: String) -> Result<Device, ()> {
         (*DEVICE_STATES.lock().unwrap()).insert(
             handle,
             LidarState::LidarStateUnknown,
         );
         Ok(Device{
             handle: handle,
+            code: code,
         })
     }
 
+    /// The broadcast code this device was connected with.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
     /// Blocks until the device reaches a state that's permissible by the given
     /// mask. Note that it does not time out, so be sure to call set_mode before
     /// calling this method!
@@ -98,12 +191,36 @@ impl Device {
     }
 
     /// Starts sampling. Returns a DataStream which can be used to retrieve data
-    /// points.
+    /// points. Uses a default channel capacity and blocks the SDK callback
+    /// thread if the consumer falls behind; use `start_sampling_with` to tune
+    /// the capacity or switch to dropping the oldest packets.
     pub fn start_sampling(&mut self) -> Result<DataStream, ()> {
-        let ds = DataStream::new(self.handle)?;
+        self.start_sampling_with(DEFAULT_CAPACITY, OverflowPolicy::Block)
+    }
+
+    /// Like `start_sampling`, but with an explicit bounded-channel capacity and
+    /// overflow policy so a slow consumer cannot grow memory without limit.
+    pub fn start_sampling_with(&mut self, capacity: usize, policy: OverflowPolicy) -> Result<DataStream, ()> {
+        let ds = DataStream::new(self.handle, self.code.clone(), capacity, policy)?;
         Ok(ds)
     }
 
+    /// Sets the extrinsic transform applied to every point emitted by this
+    /// device. Points are rotated and translated (`p' = R·p + t`) into the
+    /// user-defined frame; spherical points are transformed in Cartesian space
+    /// and converted back. Passing the identity transform restores the default.
+    pub fn set_extrinsic(&mut self, rotation: [[f32; 3]; 3], translation: [f32; 3]) {
+        let identity = Extrinsic::identity();
+        if rotation == identity.rotation && translation == identity.translation {
+            (*EXTRINSICS.lock().unwrap()).remove(&self.handle);
+            return;
+        }
+        (*EXTRINSICS.lock().unwrap()).insert(
+            self.handle,
+            Extrinsic{ rotation: rotation, translation: translation },
+        );
+    }
+
     pub fn set_coordinate_system(&mut self, system: CoordinateSystem) -> Result<(), ()> {
         let res = match system {
             CoordinateSystem::Cartesian => {
@@ -117,3 +234,113 @@ impl Device {
         Ok(())
     }
 }
+
+impl Drop for Device {
+    /// Clears the device's global state so a later device reusing the same SDK
+    /// handle cannot inherit a stale extrinsic transform.
+    fn drop(&mut self) {
+        (*EXTRINSICS.lock().unwrap()).remove(&self.handle);
+        (*DEVICE_STATES.lock().unwrap()).remove(&self.handle);
+    }
+}
+
+/// Default per-source wait used when buffering a packet for the merge.
+const DEFAULT_MERGE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Merges several `DataStream`s into a single, globally time-ordered stream.
+///
+/// The merge buffers one packet per source and always releases the one with the
+/// earliest (normalized nanosecond) timestamp, tagging it with the originating
+/// device code. This is a k-way merge over the per-device receivers, letting a
+/// user fuse several overlapping sensors into one coherent point cloud.
+///
+/// Timestamps are only comparable within a single `ClockDomain`, so all sources
+/// must report the same domain; the merge panics on a mixed-domain packet set
+/// rather than release packets out of true order.
+///
+/// A buffering step waits only up to `timeout` per source, but the iterator
+/// keeps polling while any source remains connected — an idle sensor merely
+/// delays output, it cannot end the stream. `next` returns `None` only once
+/// every source's channel has closed.
+pub struct MergedStream {
+    sources: Vec<DataStream>,
+    buffers: Vec<Option<DataPacket>>,
+    open: Vec<bool>,
+    timeout: Duration,
+}
+
+impl MergedStream {
+    /// Builds a merged stream from the given per-device streams, using the
+    /// default per-source buffering timeout.
+    pub fn new(sources: Vec<DataStream>) -> MergedStream {
+        MergedStream::new_with_timeout(sources, DEFAULT_MERGE_TIMEOUT)
+    }
+
+    /// Like `new`, but with an explicit per-source buffering timeout.
+    pub fn new_with_timeout(sources: Vec<DataStream>, timeout: Duration) -> MergedStream {
+        let buffers = sources.iter().map(|_| None).collect();
+        let open = sources.iter().map(|_| true).collect();
+        MergedStream{
+            sources: sources,
+            buffers: buffers,
+            open: open,
+            timeout: timeout,
+        }
+    }
+
+    /// Picks the buffered slot holding the globally-earliest packet. Timestamps
+    /// from different clock domains are not comparable, so refuse to merge them.
+    fn earliest_buffered(&self) -> Option<usize> {
+        let mut earliest: Option<usize> = None;
+        for i in 0..self.buffers.len() {
+            if let Some(ref packet) = self.buffers[i] {
+                if let Some(best) = earliest {
+                    let other = self.buffers[best].as_ref().unwrap();
+                    if packet.clock != other.clock {
+                        panic!("Cannot merge packets from different clock domains: {:?} vs {:?}", packet.clock, other.clock);
+                    }
+                    if packet.timestamp < other.timestamp {
+                        earliest = Some(i);
+                    }
+                } else {
+                    earliest = Some(i);
+                }
+            }
+        }
+        earliest
+    }
+}
+
+impl Iterator for MergedStream {
+    type Item = (String, DataPacket);
+
+    fn next(&mut self) -> Option<(String, DataPacket)> {
+        let timeout = self.timeout;
+        loop {
+            // Top up every empty slot from a still-open source, waiting only up
+            // to `timeout` so one quiet sensor can't stall the merge. A closed
+            // channel retires that source.
+            for i in 0..self.sources.len() {
+                if self.open[i] && self.buffers[i].is_none() {
+                    match self.sources[i].recv_status(timeout) {
+                        RecvStatus::Packet(packet) => self.buffers[i] = Some(packet),
+                        RecvStatus::Timeout => {}
+                        RecvStatus::Closed => self.open[i] = false,
+                    }
+                }
+            }
+
+            // Release the earliest buffered packet if we have one.
+            if let Some(i) = self.earliest_buffered() {
+                let packet = self.buffers[i].take().unwrap();
+                return Some((self.sources[i].device_code().to_string(), packet));
+            }
+
+            // Nothing buffered. If every source has closed we're exhausted;
+            // otherwise some source is merely idle, so keep polling.
+            if self.open.iter().all(|open| !*open) {
+                return None;
+            }
+        }
+    }
+}
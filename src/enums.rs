@@ -17,6 +17,17 @@ pub enum LidarState {
     LidarStateUnknown = 5,
 }
 
+/// What `data_cb` should do when the bounded receive channel is full because a
+/// consumer is not keeping up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    /// Block the SDK callback thread until the consumer makes room. Applies
+    /// back-pressure; no packets are lost.
+    Block,
+    /// Discard the oldest queued packet to make room for the newest one.
+    DropOldest,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LidarStateMask {
     Init = 1,
@@ -10,8 +10,11 @@ mod callbacks;
 mod datapacket;
 mod device;
 mod enums;
+mod record;
 mod sdk;
 
 pub use enums::*;
-pub use datapacket::{CartesianPoint, SphericalPoint, DataPoint, DataPacket};
+pub use datapacket::{CartesianPoint, SphericalPoint, DataPoint, DataPacket, Extrinsic, ClockDomain, ErrorFlags, PointTag, ImuSample};
+pub use device::{DataStream, PacketSource, MergedStream};
+pub use record::{Recorder, ReplaySource};
 pub use sdk::*;
@@ -0,0 +1,167 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::datapacket::DataPacket;
+use crate::device::PacketSource;
+
+/// Magic bytes at the head of every recording.
+const MAGIC: [u8; 4] = *b"LVXR";
+
+/// On-disk format revision. Bumped whenever the record layout changes.
+const FORMAT_VERSION: u8 = 1;
+
+/// Tees a stream of `DataPacket`s into a length-delimited file that can later
+/// be read back by a `ReplaySource`. The file starts with a small fixed header
+/// (magic, format version and Livox `data_type`); each record that follows is
+/// the `u64` timestamp, a `u32` payload length, and the packet's points encoded
+/// in the Livox little-endian wire layout. The points are re-encoded from the
+/// `DataPacket`, so any extrinsic transform already applied to them is kept.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    data_type: Option<u8>,
+}
+
+impl Recorder {
+    /// Creates a new recording at the given path, truncating any existing file.
+    /// The header is written lazily once the first packet fixes the data type.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Recorder> {
+        Ok(Recorder{
+            writer: BufWriter::new(File::create(path)?),
+            data_type: None,
+        })
+    }
+
+    /// Appends a single packet to the recording. Returns an error for packet
+    /// types the length-delimited format cannot round-trip (tagged
+    /// extended/dual/triple points or IMU samples), and for a packet whose type
+    /// differs from earlier records in the same file.
+    pub fn record(&mut self, packet: &DataPacket) -> io::Result<()> {
+        let data_type = match packet.recordable_type() {
+            Some(t) => t,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "packet type cannot be recorded")),
+        };
+        if let Some(existing) = self.data_type {
+            if existing != data_type {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "packet data_type changed mid-recording"));
+            }
+        }
+        let payload = packet.serialize_payload(data_type);
+        if self.data_type.is_none() {
+            self.writer.write_all(&MAGIC)?;
+            self.writer.write_u8(FORMAT_VERSION)?;
+            self.writer.write_u8(data_type)?;
+            self.data_type = Some(data_type);
+        }
+        self.writer.write_u64::<LittleEndian>(packet.timestamp)?;
+        self.writer.write_u32::<LittleEndian>(payload.len() as u32)?;
+        self.writer.write_all(&payload)?;
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Reads a recording produced by `Recorder` back as a `PacketSource`, yielding
+/// `DataPacket`s with the originally captured timestamps and points. The clock
+/// domain, PPS status and error flags are not recorded; replayed packets carry
+/// `ClockDomain::Unsynced`, `pps_locked: false` and empty error flags. Set `honor_timing` to
+/// reproduce the original inter-packet timing (reconstructed from the packet
+/// timestamps); when cleared (the default) packets are returned as fast as the
+/// file can be read.
+pub struct ReplaySource {
+    reader: BufReader<File>,
+    data_type: u8,
+    honor_timing: bool,
+    last: Option<(u64, Instant)>,
+}
+
+impl ReplaySource {
+    /// Opens a recording, validating the header.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<ReplaySource> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a livox recording"));
+        }
+        let version = reader.read_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown format version {}", version)));
+        }
+        let data_type = reader.read_u8()?;
+        Ok(ReplaySource{
+            reader: reader,
+            data_type: data_type,
+            honor_timing: false,
+            last: None,
+        })
+    }
+
+    /// Enables or disables honoring the original inter-packet timing.
+    pub fn honor_timing(mut self, honor: bool) -> Self {
+        self.honor_timing = honor;
+        self
+    }
+
+    /// Reads the next record, returning `None` at clean end-of-file.
+    fn read_record(&mut self) -> io::Result<Option<DataPacket>> {
+        let timestamp = match self.reader.read_u64::<LittleEndian>() {
+            Ok(t) => t,
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let len = self.reader.read_u32::<LittleEndian>()? as usize;
+        let mut payload = vec![0u8; len];
+        self.reader.read_exact(&mut payload)?;
+        Ok(Some(DataPacket::from_raw(self.data_type, timestamp, &payload)))
+    }
+}
+
+impl Iterator for ReplaySource {
+    type Item = DataPacket;
+
+    fn next(&mut self) -> Option<DataPacket> {
+        let packet = match self.read_record() {
+            Ok(Some(p)) => p,
+            Ok(None) => return None,
+            Err(e) => panic!("Error reading replay source: {}", e),
+        };
+        if self.honor_timing {
+            // Sleep so that the wall-clock gap between emissions matches the gap
+            // between the recorded (nanosecond) timestamps.
+            if let Some((last_ts, last_at)) = self.last {
+                if packet.timestamp > last_ts {
+                    let target = Duration::from_nanos(packet.timestamp - last_ts);
+                    let elapsed = last_at.elapsed();
+                    if target > elapsed {
+                        thread::sleep(target - elapsed);
+                    }
+                }
+            }
+            self.last = Some((packet.timestamp, Instant::now()));
+        }
+        Some(packet)
+    }
+}
+
+impl PacketSource for ReplaySource {
+    /// The timeout is ignored: this just returns the next record (or `None` at
+    /// end of file). With `honor_timing` set, the wait for the recorded
+    /// inter-packet gap can take longer than `timeout`.
+    fn next_timeout(&mut self, _timeout: Duration) -> Option<DataPacket> {
+        self.next()
+    }
+
+    /// Reads the next record, returning `None` at end of file.
+    fn next_blocking(&mut self) -> Option<DataPacket> {
+        self.next()
+    }
+}
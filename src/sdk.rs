@@ -108,7 +108,18 @@ impl Sdk {
         println!("Handle: {}", handle);
         println!("Add lidar res = {}", res);
 
-        Device::new(handle)
+        Device::new(handle, code.to_string())
+    }
+
+    /// Connects to every currently-known device, returning a `Device` for each.
+    /// Pair their `DataStream`s into a `MergedStream` to capture from several
+    /// sensors simultaneously as one time-ordered point cloud.
+    pub fn connect_all(&mut self) -> Result<Vec<Device>, ()> {
+        let mut devices = vec!();
+        for code in self.list_known_devices() {
+            devices.push(self.connect(&code)?);
+        }
+        Ok(devices)
     }
 
     /// Returns a list of known devices, as a vector of strings representing the